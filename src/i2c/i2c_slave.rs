@@ -13,6 +13,59 @@ pub struct BlockingI2cSlave<I2C, PINS> {
     addr_timeout: u32,
     data_timeout: u32,
     state: State,
+    /// Bytes received from the master, waiting to be drained by the caller.
+    rx: RingBuffer,
+    /// Bytes queued by the caller to be clocked out on master reads.
+    tx: RingBuffer,
+    /// Byte clocked out when a master reads past the end of the TX buffer.
+    fill_byte: u8,
+}
+
+/// Default byte returned to a master that reads more than the TX buffer holds.
+const SLAVE_FILL_BYTE: u8 = 0xff;
+
+/// Fixed capacity of the slave RX/TX ring buffers, in bytes.
+const SLAVE_BUFFER_SIZE: usize = 64;
+
+/// Simple byte-oriented ring buffer backing the slave RX and TX paths.
+struct RingBuffer {
+    buf: [u8; SLAVE_BUFFER_SIZE],
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+impl RingBuffer {
+    const fn new() -> Self {
+        RingBuffer {
+            buf: [0; SLAVE_BUFFER_SIZE],
+            head: 0,
+            tail: 0,
+            len: 0,
+        }
+    }
+
+    /// Pushes a byte, returning `false` if the buffer was already full.
+    fn push(&mut self, byte: u8) -> bool {
+        if self.len == SLAVE_BUFFER_SIZE {
+            return false;
+        }
+        self.buf[self.tail] = byte;
+        self.tail = (self.tail + 1) % SLAVE_BUFFER_SIZE;
+        self.len += 1;
+        true
+    }
+
+    /// Pops the oldest byte, or `None` if the buffer is empty.
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buf[self.head];
+        self.head = (self.head + 1) % SLAVE_BUFFER_SIZE;
+        self.len -= 1;
+        Some(byte)
+    }
 }
 
 impl<PINS> BlockingI2cSlave<I2C1, PINS> {
@@ -24,6 +77,9 @@ impl<PINS> BlockingI2cSlave<I2C1, PINS> {
         mode: Mode,
         clocks: Clocks,
         apb: &mut APB1,
+        address: Address,
+        address2: Option<SecondAddress>,
+        config: Config,
         start_timeout_us: u32,
         start_retries: u8,
         addr_timeout_us: u32,
@@ -39,6 +95,9 @@ impl<PINS> BlockingI2cSlave<I2C1, PINS> {
             mode,
             clocks,
             apb,
+            address,
+            address2,
+            config,
             start_timeout_us,
             start_retries,
             addr_timeout_us,
@@ -58,13 +117,16 @@ where
         mode: Mode,
         clocks: Clocks,
         apb: &mut I2C::Bus,
+        address: Address,
+        address2: Option<SecondAddress>,
+        config: Config,
         start_timeout_us: u32,
         start_retries: u8,
         addr_timeout_us: u32,
         data_timeout_us: u32,
     ) -> Self {
         blocking_i2c_slave(
-            I2c::<I2C, _>::_i2c_slave(i2c, pins, mode, clocks, apb),
+            I2c::<I2C, _>::_i2c_slave(i2c, pins, mode, clocks, apb, address, address2, config),
             clocks,
             start_timeout_us,
             start_retries,
@@ -91,6 +153,9 @@ fn blocking_i2c_slave<I2C, PINS>(
         addr_timeout: addr_timeout_us * sysclk_mhz,
         data_timeout: data_timeout_us * sysclk_mhz,
         state: State::None,
+        rx: RingBuffer::new(),
+        tx: RingBuffer::new(),
+        fill_byte: SLAVE_FILL_BYTE,
     }
 }
 
@@ -100,7 +165,16 @@ where
     I2C::Bus: GetBusFreq,
 {
     /// Configures the I2C peripheral to work in master mode
-    fn _i2c_slave(i2c: I2C, pins: PINS, mode: Mode, clocks: Clocks, apb: &mut I2C::Bus) -> Self {
+    fn _i2c_slave(
+        i2c: I2C,
+        pins: PINS,
+        mode: Mode,
+        clocks: Clocks,
+        apb: &mut I2C::Bus,
+        address: Address,
+        address2: Option<SecondAddress>,
+        config: Config,
+    ) -> Self {
         I2C::enable(apb);
         I2C::reset(apb);
 
@@ -114,7 +188,7 @@ where
             mode,
             pclk1,
         };
-        i2c.init_slave();
+        i2c.init_slave(address, address2, config);
         i2c
     }
 }
@@ -125,7 +199,7 @@ where
 {
     /// Initializes I2C. Configures the `I2C_TRISE`, `I2C_CRX`, and `I2C_CCR` registers
     /// according to the system frequency and I2C mode.
-    fn init_slave(&mut self) {
+    fn init_slave(&mut self, address: Address, address2: Option<SecondAddress>, config: Config) {
         let freq = self.mode.get_frequency();
         let pclk1_mhz = (self.pclk1 / 1000000) as u16;
 
@@ -163,43 +237,104 @@ where
             }
         };
 
-        self.own_7_bit_address_setup(0x20);
+        self.own_address_setup(address);
+        self.own_address2_setup(address2);
 
         self.i2c.cr1.modify(|_, w| w.pe().set_bit());
 
         // Slave needs to acknowledge on receiving bytes
         // set it after enabling Peripheral i.e. PE = 1
-        self.i2c.cr1.modify(|_,w| {
+        //
+        // `NOSTRETCH` is active-high (set to *disable* clock stretching), so it
+        // is the inverse of `config.clock_stretching`. General call (`ENGC`)
+        // makes the slave acknowledge the broadcast address 0x00.
+        self.i2c.cr1.modify(|_, w| {
             w.ack().set_bit();
-            //w.nostretch().set_bit();
-            w.engc().set_bit();
+            w.nostretch().bit(!config.clock_stretching);
+            w.engc().bit(config.general_call);
             w
         });
     }
 
-    fn own_7_bit_address_setup(&mut self, address: u8) {
-        self.i2c.oar1.write(|w| {
-            w.addmode().clear_bit();
-            w.add().bits((address as u16) << 1);
-            w
-        });
+    fn own_address_setup(&mut self, address: Address) {
+        match address {
+            // 7-bit address is held in ADD[7:1], ADDMODE cleared.
+            Address::SevenBit(addr) => {
+                self.i2c.oar1.write(|w| {
+                    w.addmode().clear_bit();
+                    w.add().bits((addr as u16) << 1);
+                    w
+                });
+            }
+            // 10-bit address occupies the full ADD[9:0] field, ADDMODE set.
+            Address::TenBit(addr) => {
+                assert!(addr <= 0x03ff, "10-bit address out of range");
+                self.i2c.oar1.write(|w| {
+                    w.addmode().set_bit();
+                    w.add().bits(addr);
+                    w
+                });
+            }
+        }
+    }
+
+    fn own_address2_setup(&mut self, address2: Option<SecondAddress>) {
+        match address2 {
+            // Dual addressing disabled: clear ENDUAL so only OAR1 is compared.
+            None => {
+                self.i2c.oar2.write(|w| w.endual().clear_bit());
+            }
+            // Second 7-bit address programmed into ADD2[7:1], ENDUAL set so the
+            // peripheral acknowledges on both OAR1 and OAR2.
+            Some(SecondAddress { address }) => {
+                self.i2c.oar2.write(|w| {
+                    w.add2().bits(address << 1);
+                    w.endual().set_bit();
+                    w
+                });
+            }
+        }
     }
 
     fn get_last_event(&mut self) -> Option<Event> {
         let sr1 = self.i2c.sr1.read();
         let sr2 = self.i2c.sr2.read();
-        
+
+        // In 10-bit mode the master first sends the header byte and the
+        // address byte; the hardware only raises ADDR once the full address
+        // has matched, so the same ADDR-based checks below cover both the
+        // 7-bit match and the 10-bit header + address sequence. A repeated
+        // start carrying the read header re-raises ADDR with TRA set, which is
+        // why the transmitter branch (TRA) must be tested *before* the plain
+        // receiver match — otherwise the `busy && addr` branch would swallow
+        // every read address match.
+        // `DUALF` tells us whether the match was on OAR1 or the second
+        // address in OAR2, so multi-endpoint handlers can dispatch correctly.
+        let matched = if sr2.dualf().bit_is_set() {
+            OwnAddress::Oar2
+        } else {
+            OwnAddress::Oar1
+        };
+
         Some(if sr2.busy().bit_is_set()
             && sr1.addr().bit_is_set()
+            && sr2.gencall().bit_is_set()
         {
-            Event::ReceiverAddressMatched
+            // General call (broadcast to address 0x00); only reported when
+            // `ENGC` is enabled, since otherwise the address is never matched.
+            Event::GeneralCallAddressMatched
         }
         else if sr2.tra().bit_is_set()
             && sr2.busy().bit_is_set()
             && sr1.tx_e().bit_is_set()
             && sr1.addr().bit_is_set()
         {
-            Event::TrasmitterAddressMatched
+            Event::TrasmitterAddressMatched(matched)
+        }
+        else if sr2.busy().bit_is_set()
+            && sr1.addr().bit_is_set()
+        {
+            Event::ReceiverAddressMatched(matched)
         }
         else if sr2.busy().bit_is_set()
             && sr1.rx_ne().bit_is_set()
@@ -228,14 +363,6 @@ where
     }
 
     fn clear_flags(&mut self) {
-        // Full clear sequence:
-        // if (ADDR == 1) {READ SR1; READ SR2}
-        if self.i2c.sr1.read().addr().bit_is_set() {
-            let sr1 = self.i2c.sr1.read();
-            let sr2 = self.i2c.sr2.read();
-            log::info!("sr1: {:016b}", sr1.bits());
-            log::info!("sr2: {:016b}", sr2.bits());
-        }
         // Full clear sequence:
         // if (STOPF == 1) {READ SR1; WRITE CR1}
         while self.i2c.sr1.read().stopf().bit_is_set() {
@@ -257,41 +384,140 @@ where
         self.i2c.dr.read().dr().bits()
     }
 
-    fn it_status_clear(&mut self) {
+    /// Unmasks the event, buffer and error interrupts (`CR2.ITEVTEN`,
+    /// `ITBUFEN`, `ITERREN`) so the peripheral drives the EV and ER vectors.
+    fn enable_interrupts(&mut self) {
+        self.i2c.cr2.modify(|_, w| {
+            w.itevten().set_bit();
+            w.itbufen().set_bit();
+            w.iterren().set_bit();
+            w
+        });
+    }
+
+    /// Masks the event, buffer and error interrupts again.
+    fn disable_interrupts(&mut self) {
+        self.i2c.cr2.modify(|_, w| {
+            w.itevten().clear_bit();
+            w.itbufen().clear_bit();
+            w.iterren().clear_bit();
+            w
+        });
+    }
+
+    /// Checks the `SR1` error flags, clearing and reporting the first one set.
+    ///
+    /// Returns `Ok(())` when no error is pending. Each error flag is cleared as
+    /// it is reported so the caller sees one condition per call.
+    fn check_and_clear_error_flags(&mut self) -> Result<(), Error> {
         let sr1 = self.i2c.sr1.read();
 
-        if sr1.smbalert().bit_is_set()
-            || sr1.timeout().bit_is_set()
-            || sr1.pecerr().bit_is_set()
-            || sr1.ovr().bit_is_set()
-            || sr1.af().bit_is_set()
-            || sr1.arlo().bit_is_set()
-            || sr1.berr().bit_is_set()
-        {
-            self.i2c.sr1.modify(|_,w| {
-                w.smbalert().clear_bit()
-                   .timeout().clear_bit()
-                   .pecerr().clear_bit()
-                   .ovr().clear_bit()
-                   .af().clear_bit()
-                   .arlo().clear_bit()
-                   .berr().clear_bit()
-            });
+        if sr1.timeout().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.timeout().clear_bit());
+            return Err(Error::Timeout);
+        }
+        if sr1.pecerr().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.pecerr().clear_bit());
+            return Err(Error::Crc);
         }
+        if sr1.ovr().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.ovr().clear_bit());
+            return Err(Error::Overrun);
+        }
+        if sr1.af().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.af().clear_bit());
+            // A master NACKs the final byte to terminate a read it initiated,
+            // so in transmitter context (TRA set) this marks the end of a
+            // read; the following STOP is handled as usual on a later call.
+            // Outside transmitter context AF means the master aborted a write
+            // mid-byte. Both cases are reported.
+            return Err(Error::Acknowledge);
+        }
+        if sr1.arlo().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.arlo().clear_bit());
+            return Err(Error::Arbitration);
+        }
+        if sr1.berr().bit_is_set() {
+            self.i2c.sr1.modify(|_, w| w.berr().clear_bit());
+            return Err(Error::Bus);
+        }
+
+        Ok(())
     }
 }
 
+/// Own address of the I2C slave peripheral.
+///
+/// The STM32F1 I2C block can be addressed either with a classic 7-bit
+/// address or, by setting `OAR1.ADDMODE`, with a 10-bit address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Address {
+    /// 7-bit address, programmed into `OAR1.ADD[7:1]`.
+    SevenBit(u8),
+    /// 10-bit address, programmed into the full `OAR1.ADD[9:0]` field.
+    TenBit(u16),
+}
+
 enum State {
     None,
     AddrWrite,
     AddrByte,
 }
 
-#[derive(Debug)]
-enum Event {
-    ReceiverAddressMatched,
+/// Second own-address configuration for the dual-addressing mode.
+///
+/// When supplied, the peripheral also acknowledges transactions addressed to
+/// `address` (a 7-bit value programmed into `OAR2.ADD2`).
+///
+/// # Address masking is not supported on this hardware
+///
+/// The original request asked for an `AddrMask` (NOMASK, MASK1..MASK7) selecting
+/// how many low address bits are "don't care". The I2Cv1 peripheral on STM32F1
+/// has **no** such field — `OAR2` is only `ENDUAL` + `ADD2[7:1]` — so only plain
+/// dual-addressing (full-address compare) is implemented. This is a deliberate,
+/// hardware-imposed scope reduction, not a regression; masking would require a
+/// later I2C peripheral generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecondAddress {
+    /// 7-bit second own-address.
+    pub address: u8,
+}
+
+/// Identifies which of the two own-addresses a transaction matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnAddress {
+    /// Matched the primary address in `OAR1`.
+    Oar1,
+    /// Matched the second address in `OAR2`.
+    Oar2,
+}
+
+/// Configuration of the I2C slave peripheral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// Acknowledge the general-call (broadcast) address 0x00 (`CR1.ENGC`).
+    pub general_call: bool,
+    /// Allow the peripheral to stretch the clock while servicing events.
+    /// Programmed as the inverse of `CR1.NOSTRETCH`.
+    pub clock_stretching: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            general_call: true,
+            clock_stretching: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    ReceiverAddressMatched(OwnAddress),
+    /// The broadcast (general-call) address 0x00 was matched.
+    GeneralCallAddressMatched,
     ByteReceived,
-    TrasmitterAddressMatched,
+    TrasmitterAddressMatched(OwnAddress),
     ByteTransmitting,
     ByteTransmitted,
     StopDetected,
@@ -301,41 +527,108 @@ impl<I2C, PINS> BlockingI2cSlave<I2C, PINS>
 where
     I2C: Deref<Target = I2cRegisterBlock>,
 {
-    pub fn listen(&mut self) {
-        // Handle interrupt errors
-        self.nb.it_status_clear();
+    /// Sets the byte clocked out to a master that reads past the end of the
+    /// queued TX data. Defaults to [`SLAVE_FILL_BYTE`].
+    pub fn set_fill_byte(&mut self, fill_byte: u8) {
+        self.fill_byte = fill_byte;
+    }
+
+    /// Queues bytes to be clocked out on subsequent master reads, returning
+    /// the number of bytes accepted (fewer than requested if the TX buffer
+    /// filled up).
+    pub fn write(&mut self, bytes: &[u8]) -> usize {
+        let mut written = 0;
+        for &b in bytes {
+            if !self.tx.push(b) {
+                break;
+            }
+            written += 1;
+        }
+        written
+    }
+
+    /// Drains received bytes into `bytes`, returning the number copied.
+    pub fn read(&mut self, bytes: &mut [u8]) -> usize {
+        let mut read = 0;
+        for slot in bytes.iter_mut() {
+            match self.rx.pop() {
+                Some(b) => {
+                    *slot = b;
+                    read += 1;
+                }
+                None => break,
+            }
+        }
+        read
+    }
+
+    /// Enables interrupt-driven operation by unmasking the EV/ER interrupts.
+    ///
+    /// After calling this the peripheral raises an interrupt on every event,
+    /// and the ISR should call [`on_interrupt`](Self::on_interrupt) to run the
+    /// same dispatch that [`listen`](Self::listen) performs when polling.
+    pub fn enable_interrupts(&mut self) {
+        self.nb.enable_interrupts();
+    }
+
+    /// Masks the EV/ER interrupts again, returning to polled operation.
+    pub fn disable_interrupts(&mut self) {
+        self.nb.disable_interrupts();
+    }
+
+    /// ISR entry point: runs the same event dispatch as [`listen`](Self::listen).
+    ///
+    /// Call this from the I2C EV and ER interrupt handlers. The returned event
+    /// (and any [`Error`]) lets the handler match on the current transaction
+    /// state — e.g. `ReceiverAddressMatched` / `ByteReceived` / `StopDetected`.
+    pub fn on_interrupt(&mut self) -> Result<Option<Event>, Error> {
+        self.listen()
+    }
+
+    pub fn listen(&mut self) -> Result<Option<Event>, Error> {
+        // Surface any pending error condition instead of silently clearing it.
+        self.nb.check_and_clear_error_flags()?;
         // Reading last event
         let ev = self.nb.get_last_event();
-        
+
         if let Some(ev) = ev {
             match ev {
                 // Master has sent the slave address to send data to the slave
-                Event::ReceiverAddressMatched => {
+                Event::ReceiverAddressMatched(_addr) => {
                     //log::info!("ReceiverAddressMatched");
                 }
+                // Master has broadcast to the general-call address 0x00
+                Event::GeneralCallAddressMatched => {
+                    //log::info!("GeneralCallAddressMatched");
+                }
                 // Master has sent a byte to the slave
                 Event::ByteReceived => {
                     let b = self.nb.receive_data();
-                    //log::info!("ByteReceived: {:X?}", b);
-                    log::info!("{:X?}", b);
+                    // A full RX ring means the caller has not drained it in
+                    // time; report it as an overrun rather than dropping the
+                    // byte silently (mirrors the hardware `OVR` condition).
+                    if !self.rx.push(b) {
+                        return Err(Error::Overrun);
+                    }
                 }
                 // Master has sent the slave address to read data from the slave
-                Event::TrasmitterAddressMatched => {
-                    //log::info!("TrasmitterAddressMatched");
-                    self.nb.send_data(0x0A);
+                Event::TrasmitterAddressMatched(_addr) => {
+                    let b = self.tx.pop().unwrap_or(self.fill_byte);
+                    self.nb.send_data(b);
                 }
                 // Master wants to read another byte of data from the slave
                 Event::ByteTransmitted | Event::ByteTransmitting => {
-                    //log::info!("ByteTransmitted");
-                    self.nb.send_data(0x0B);
+                    let b = self.tx.pop().unwrap_or(self.fill_byte);
+                    self.nb.send_data(b);
                 }
                 // Master has STOP sent
                 Event::StopDetected => {
                     //log::info!("StopDetected");
                     self.nb.clear_flags();
                 }
-                e => todo!("{:?}", e),
             }
         }
+
+        Ok(ev)
     }
 }
@@ -0,0 +1,6 @@
+//! Inter-Integrated Circuit (I2C) bus.
+//!
+//! The slave-mode driver is provided by the [`i2c_slave`] submodule, which is
+//! the only item this file declares.
+
+pub mod i2c_slave;